@@ -0,0 +1,243 @@
+//! Dense slot map: packed value storage for cache-friendly iteration.
+//!
+//! `SlotMapVec` interleaves values and free-list links in the same `Entry<T>`
+//! array, so iteration visits vacant slots too and values are not contiguous.
+//! `DenseSlotMapVec` instead keeps a packed `values`/`keys` pair and a
+//! separate `Vec<Slot>` of version/indirection records. `iter`/`iter_mut`
+//! become a straight walk over a contiguous slice (good for numeric/SIMD
+//! workloads), and `remove` swap-removes the dense arrays in O(1) instead of
+//! leaving a hole, at the cost of one extra indirection on `get`.
+
+use std::ops;
+
+/// An index into a `DenseSlotMapVec`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SlotMapIndex {
+    slot: u32,
+    version: u32,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Slot {
+    version: u32,
+    // Index into `values`/`keys` when occupied (odd version), or the next
+    // free slot when vacant (even version).
+    idx_or_free: u32,
+}
+
+/// A slot map that stores values packed in a contiguous `Vec<T>`.
+///
+/// See the [module documentation](index.html) for the tradeoffs versus
+/// [`SlotMapVec`](crate::SlotMapVec).
+pub struct DenseSlotMapVec<T> {
+    slots: Vec<Slot>,
+    next_free: usize,
+    values: Vec<T>,
+    // Parallel to `values`: the key that currently owns each dense slot, so
+    // `remove`'s swap can find and patch the displaced element's `Slot`.
+    keys: Vec<SlotMapIndex>,
+}
+
+impl<T> Default for DenseSlotMapVec<T> {
+    fn default() -> Self {
+        DenseSlotMapVec::new()
+    }
+}
+
+impl<T> DenseSlotMapVec<T> {
+    /// Construct a new, empty `DenseSlotMapVec`.
+    pub fn new() -> DenseSlotMapVec<T> {
+        DenseSlotMapVec {
+            slots: Vec::new(),
+            next_free: 0,
+            values: Vec::new(),
+            keys: Vec::new(),
+        }
+    }
+
+    /// Returns the number of stored values.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns `true` if no values are stored in the map.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the stored values as a contiguous slice.
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    /// Returns the stored values as a contiguous mutable slice.
+    pub fn values_mut(&mut self) -> &mut [T] {
+        &mut self.values
+    }
+
+    /// Insert a value into the map, returning the index to the value.
+    pub fn insert(&mut self, val: T) -> SlotMapIndex {
+        let dense_idx = self.values.len() as u32;
+
+        let key = if self.next_free == self.slots.len() {
+            let slot = self.slots.len();
+            self.slots.push(Slot {
+                version: 1,
+                idx_or_free: dense_idx,
+            });
+            self.next_free += 1;
+            SlotMapIndex {
+                slot: slot as u32,
+                version: 1,
+            }
+        } else {
+            let slot = self.next_free;
+            self.next_free = self.slots[slot].idx_or_free as usize;
+            let version = self.slots[slot].version + 1;
+            self.slots[slot] = Slot {
+                version,
+                idx_or_free: dense_idx,
+            };
+            SlotMapIndex {
+                slot: slot as u32,
+                version,
+            }
+        };
+
+        self.values.push(val);
+        self.keys.push(key);
+        key
+    }
+
+    fn dense_index(&self, key: SlotMapIndex) -> Option<usize> {
+        match self.slots.get(key.slot as usize) {
+            Some(slot) if slot.version == key.version && slot.version % 2 == 1 => {
+                Some(slot.idx_or_free as usize)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the value associated with the given key.
+    pub fn get(&self, key: SlotMapIndex) -> Option<&T> {
+        self.dense_index(key).map(|i| &self.values[i])
+    }
+
+    /// Returns a mutable reference to the value associated with the given key.
+    pub fn get_mut(&mut self, key: SlotMapIndex) -> Option<&mut T> {
+        self.dense_index(key).map(move |i| &mut self.values[i])
+    }
+
+    /// Return `true` if a value is associated with the given key.
+    pub fn contains(&self, key: SlotMapIndex) -> bool {
+        self.dense_index(key).is_some()
+    }
+
+    /// Removes and returns the value associated with the given key.
+    pub fn remove(&mut self, key: SlotMapIndex) -> Option<T> {
+        let dense_idx = self.dense_index(key)?;
+
+        let slot = &mut self.slots[key.slot as usize];
+        slot.version += 1;
+        slot.idx_or_free = self.next_free as u32;
+        self.next_free = key.slot as usize;
+
+        self.keys.swap_remove(dense_idx);
+        let val = self.values.swap_remove(dense_idx);
+
+        if dense_idx < self.values.len() {
+            let moved_key = self.keys[dense_idx];
+            self.slots[moved_key.slot as usize].idx_or_free = dense_idx as u32;
+        }
+
+        Some(val)
+    }
+
+    /// Return an iterator over all elements of the map along with their index.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            keys: self.keys.iter(),
+            values: self.values.iter(),
+        }
+    }
+
+    /// Return an iterator over mutable references to all elements of the map
+    /// along with their index.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            keys: self.keys.iter(),
+            values: self.values.iter_mut(),
+        }
+    }
+}
+
+impl<T> ops::Index<SlotMapIndex> for DenseSlotMapVec<T> {
+    type Output = T;
+    fn index(&self, key: SlotMapIndex) -> &T {
+        self.get(key).expect("invalid key")
+    }
+}
+
+impl<T> ops::IndexMut<SlotMapIndex> for DenseSlotMapVec<T> {
+    fn index_mut(&mut self, key: SlotMapIndex) -> &mut T {
+        self.get_mut(key).expect("invalid key")
+    }
+}
+
+/// An iterator over the values stored in a `DenseSlotMapVec`.
+pub struct Iter<'a, T: 'a> {
+    keys: std::slice::Iter<'a, SlotMapIndex>,
+    values: std::slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (SlotMapIndex, &'a T);
+    fn next(&mut self) -> Option<(SlotMapIndex, &'a T)> {
+        Some((*self.keys.next()?, self.values.next()?))
+    }
+}
+
+/// A mutable iterator over the values stored in a `DenseSlotMapVec`.
+pub struct IterMut<'a, T: 'a> {
+    keys: std::slice::Iter<'a, SlotMapIndex>,
+    values: std::slice::IterMut<'a, T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = (SlotMapIndex, &'a mut T);
+    fn next(&mut self) -> Option<(SlotMapIndex, &'a mut T)> {
+        Some((*self.keys.next()?, self.values.next()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_get_remove() {
+        let mut x = DenseSlotMapVec::new();
+        let a = x.insert(1);
+        let b = x.insert(2);
+        let c = x.insert(3);
+        assert_eq!(x.get(a), Some(&1));
+        x.remove(b);
+        assert_eq!(x.get(b), None);
+        assert_eq!(x.get(c), Some(&3));
+        assert_eq!(x.values(), &[1, 3]);
+    }
+
+    #[test]
+    fn swap_remove_patches_moved_slot() {
+        let mut x = DenseSlotMapVec::new();
+        let keys: Vec<_> = (0..5).map(|i| x.insert(i)).collect();
+        x.remove(keys[1]);
+        for (i, k) in keys.iter().enumerate() {
+            if i == 1 {
+                assert_eq!(x.get(*k), None);
+            } else {
+                assert_eq!(x.get(*k), Some(&i));
+            }
+        }
+    }
+}
@@ -0,0 +1,391 @@
+//! Hop-iteration slot map: iterates in O(occupied) time instead of O(capacity).
+//!
+//! `SlotMapVec`'s `Iter`/`IterMut` walk every slot, so iterating a map that
+//! once held millions of elements but now holds a handful still costs
+//! O(capacity). `HopSlotMapVec` keeps the free list as a doubly-linked list
+//! of contiguous vacant *blocks*, so the two boundary slots of each block
+//! point at each other (`other_end`) and at the neighboring blocks
+//! (`next`/`prev`). Iteration reads `other_end` on a vacant slot and jumps
+//! the cursor straight past the whole run. This makes `insert`/`remove`
+//! roughly 2x slower (they must maintain block boundaries and coalesce
+//! neighbors) in exchange for iteration that costs only O(len).
+
+use std::mem;
+use std::ops;
+
+/// Sentinel meaning "no block" in the free list.
+const NONE: u32 = u32::MAX;
+
+/// An index into a `HopSlotMapVec`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SlotMapIndex {
+    slot: u32,
+    version: u32,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct FreeListEntry {
+    // Start slot of the next vacant block in the free list, or `NONE`.
+    next: u32,
+    // Start slot of the previous vacant block in the free list, or `NONE`.
+    prev: u32,
+    // The slot at the opposite end of this contiguous vacant block.
+    other_end: u32,
+}
+
+enum Occupation<T> {
+    Occupied(T),
+    // Only meaningful when stored at a block's start or end slot; slots in
+    // the interior of a block are never read.
+    Vacant(FreeListEntry),
+}
+
+struct Entry<T> {
+    version: u32,
+    content: Occupation<T>,
+}
+
+/// A slot map that skips runs of vacant slots while iterating.
+///
+/// See the [module documentation](index.html) for the tradeoffs versus
+/// [`SlotMapVec`](crate::SlotMapVec).
+pub struct HopSlotMapVec<T> {
+    entries: Vec<Entry<T>>,
+    // Start slot of the head block in the free list, or `entries.len()` if
+    // there are no free slots at all.
+    free_head: usize,
+    len: usize,
+}
+
+impl<T> Default for HopSlotMapVec<T> {
+    fn default() -> Self {
+        HopSlotMapVec::new()
+    }
+}
+
+impl<T> HopSlotMapVec<T> {
+    /// Construct a new, empty `HopSlotMapVec`.
+    pub fn new() -> HopSlotMapVec<T> {
+        HopSlotMapVec {
+            entries: Vec::new(),
+            free_head: 0,
+            len: 0,
+        }
+    }
+
+    /// Returns the number of stored values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no values are stored in the map.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn vacant_at(&self, slot: u32) -> FreeListEntry {
+        match self.entries[slot as usize].content {
+            Occupation::Vacant(fle) => fle,
+            Occupation::Occupied(_) => panic!("inconsistent internal state in HopSlotMapVec"),
+        }
+    }
+
+    fn is_vacant(&self, slot: usize) -> bool {
+        matches!(self.entries[slot].content, Occupation::Vacant(_))
+    }
+
+    // Writes matching boundary copies for a block spanning `start..=end`.
+    fn write_block(&mut self, start: u32, end: u32, next: u32, prev: u32) {
+        self.entries[start as usize].content = Occupation::Vacant(FreeListEntry {
+            next,
+            prev,
+            other_end: end,
+        });
+        if end != start {
+            self.entries[end as usize].content = Occupation::Vacant(FreeListEntry {
+                next,
+                prev,
+                other_end: start,
+            });
+        }
+    }
+
+    fn set_next(&mut self, block_start: u32, next: u32) {
+        if block_start == NONE {
+            return;
+        }
+        let fle = self.vacant_at(block_start);
+        self.write_block(block_start, fle.other_end, next, fle.prev);
+    }
+
+    fn set_prev(&mut self, block_start: u32, prev: u32) {
+        if block_start == NONE {
+            return;
+        }
+        let fle = self.vacant_at(block_start);
+        self.write_block(block_start, fle.other_end, fle.next, prev);
+    }
+
+    /// Insert a value into the map, returning the index to the value.
+    pub fn insert(&mut self, val: T) -> SlotMapIndex {
+        if self.free_head == self.entries.len() {
+            let slot = self.entries.len();
+            self.entries.push(Entry {
+                version: 0,
+                content: Occupation::Occupied(val),
+            });
+            self.free_head = self.entries.len();
+            self.len += 1;
+            return SlotMapIndex {
+                slot: slot as u32,
+                version: 0,
+            };
+        }
+
+        let slot = self.free_head as u32;
+        let fle = self.vacant_at(slot);
+        let version = self.entries[slot as usize].version.wrapping_add(1);
+
+        if fle.other_end == slot {
+            // Single-slot block: consuming it removes the block entirely.
+            self.set_next(fle.prev, fle.next);
+            self.set_prev(fle.next, fle.prev);
+            self.free_head = if fle.next == NONE {
+                self.entries.len()
+            } else {
+                fle.next as usize
+            };
+        } else {
+            // Shrink the block from the front; its start moves to `slot + 1`.
+            let new_start = slot + 1;
+            self.write_block(new_start, fle.other_end, fle.next, fle.prev);
+            self.set_prev(fle.next, new_start);
+            self.set_next(fle.prev, new_start);
+            self.free_head = new_start as usize;
+        }
+
+        self.entries[slot as usize] = Entry {
+            version,
+            content: Occupation::Occupied(val),
+        };
+        self.len += 1;
+        SlotMapIndex { slot, version }
+    }
+
+    /// Returns a reference to the value associated with the given key.
+    pub fn get(&self, key: SlotMapIndex) -> Option<&T> {
+        match self.entries.get(key.slot as usize) {
+            Some(&Entry { version, content: Occupation::Occupied(ref obj) }) if version == key.version => Some(obj),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the value associated with the given key.
+    pub fn get_mut(&mut self, key: SlotMapIndex) -> Option<&mut T> {
+        match self.entries.get_mut(key.slot as usize) {
+            Some(&mut Entry { version, content: Occupation::Occupied(ref mut obj) }) if version == key.version => {
+                Some(obj)
+            }
+            _ => None,
+        }
+    }
+
+    /// Return `true` if a value is associated with the given key.
+    pub fn contains(&self, key: SlotMapIndex) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Removes and returns the value associated with the given key, coalescing
+    /// the freed slot with any adjacent vacant blocks.
+    pub fn remove(&mut self, key: SlotMapIndex) -> Option<T> {
+        let slot = key.slot as usize;
+        match self.entries.get(slot) {
+            Some(entry) if entry.version == key.version && matches!(entry.content, Occupation::Occupied(_)) => {}
+            _ => return None,
+        }
+
+        let left_vacant = slot > 0 && self.is_vacant(slot - 1);
+        let right_vacant = slot + 1 < self.entries.len() && self.is_vacant(slot + 1);
+
+        let placeholder = Occupation::Vacant(FreeListEntry {
+            next: NONE,
+            prev: NONE,
+            other_end: slot as u32,
+        });
+        let val = match mem::replace(&mut self.entries[slot].content, placeholder) {
+            Occupation::Occupied(v) => v,
+            Occupation::Vacant(_) => unreachable!(),
+        };
+
+        match (left_vacant, right_vacant) {
+            (false, false) => {
+                let old_head = if self.free_head == self.entries.len() {
+                    NONE
+                } else {
+                    self.free_head as u32
+                };
+                self.write_block(slot as u32, slot as u32, old_head, NONE);
+                self.set_prev(old_head, slot as u32);
+                self.free_head = slot;
+            }
+            (true, false) => {
+                let left_end = (slot - 1) as u32;
+                let fle = self.vacant_at(left_end);
+                let left_start = fle.other_end;
+                self.write_block(left_start, slot as u32, fle.next, fle.prev);
+            }
+            (false, true) => {
+                let right_start = (slot + 1) as u32;
+                let fle = self.vacant_at(right_start);
+                let right_end = fle.other_end;
+                self.write_block(slot as u32, right_end, fle.next, fle.prev);
+                self.set_prev(fle.next, slot as u32);
+                self.set_next(fle.prev, slot as u32);
+                if self.free_head == right_start as usize {
+                    self.free_head = slot;
+                }
+            }
+            (true, true) => {
+                let left_end = (slot - 1) as u32;
+                let right_start = (slot + 1) as u32;
+                let left_fle = self.vacant_at(left_end);
+                let right_fle = self.vacant_at(right_start);
+                let left_start = left_fle.other_end;
+                let right_end = right_fle.other_end;
+
+                self.set_next(right_fle.prev, right_fle.next);
+                self.set_prev(right_fle.next, right_fle.prev);
+                if self.free_head == right_start as usize {
+                    self.free_head = if right_fle.next == NONE {
+                        self.entries.len()
+                    } else {
+                        right_fle.next as usize
+                    };
+                }
+
+                // If the left and right blocks were also neighbors in the free
+                // list (not just physically adjacent), unlinking the right
+                // block above already rewrote the left block's own
+                // `next`/`prev`. Re-read them instead of reusing the
+                // now-stale `left_fle`, or the merged block would end up with
+                // dangling links into the slot we just removed.
+                let left_fle = self.vacant_at(left_start);
+                self.write_block(left_start, right_end, left_fle.next, left_fle.prev);
+            }
+        }
+
+        self.len -= 1;
+        Some(val)
+    }
+
+    /// Return an iterator over all elements of the map along with their index.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            map: self,
+            curr: 0,
+        }
+    }
+}
+
+impl<T> ops::Index<SlotMapIndex> for HopSlotMapVec<T> {
+    type Output = T;
+    fn index(&self, key: SlotMapIndex) -> &T {
+        self.get(key).expect("invalid key")
+    }
+}
+
+impl<T> ops::IndexMut<SlotMapIndex> for HopSlotMapVec<T> {
+    fn index_mut(&mut self, key: SlotMapIndex) -> &mut T {
+        self.get_mut(key).expect("invalid key")
+    }
+}
+
+/// An iterator over the values stored in a `HopSlotMapVec`.
+pub struct Iter<'a, T: 'a> {
+    map: &'a HopSlotMapVec<T>,
+    curr: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (SlotMapIndex, &'a T);
+
+    fn next(&mut self) -> Option<(SlotMapIndex, &'a T)> {
+        while self.curr < self.map.entries.len() {
+            let entry = &self.map.entries[self.curr];
+            match entry.content {
+                Occupation::Occupied(ref value) => {
+                    let key = SlotMapIndex {
+                        slot: self.curr as u32,
+                        version: entry.version,
+                    };
+                    self.curr += 1;
+                    return Some((key, value));
+                }
+                Occupation::Vacant(fle) => {
+                    self.curr = fle.other_end as usize + 1;
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_get_remove() {
+        let mut x = HopSlotMapVec::new();
+        let a = x.insert(1);
+        let b = x.insert(2);
+        let c = x.insert(3);
+        assert_eq!(x.get(a), Some(&1));
+        x.remove(b);
+        assert_eq!(x.get(b), None);
+        assert_eq!(x.get(c), Some(&3));
+        let d = x.insert(4);
+        assert_eq!(x.get(d), Some(&4));
+    }
+
+    #[test]
+    fn iteration_skips_vacant_runs() {
+        let mut x = HopSlotMapVec::new();
+        let keys: Vec<_> = (0..10).map(|i| x.insert(i)).collect();
+        for k in &keys[2..8] {
+            x.remove(*k);
+        }
+        let vals: Vec<_> = x.iter().map(|(_, v)| *v).collect();
+        assert_eq!(vals, vec![0, 1, 8, 9]);
+    }
+
+    #[test]
+    fn coalesces_adjacent_blocks() {
+        let mut x = HopSlotMapVec::new();
+        let keys: Vec<_> = (0..5).map(|i| x.insert(i)).collect();
+        x.remove(keys[1]);
+        x.remove(keys[3]);
+        x.remove(keys[2]);
+        let vals: Vec<_> = x.iter().map(|(_, v)| *v).collect();
+        assert_eq!(vals, vec![0, 4]);
+    }
+
+    #[test]
+    fn reuses_every_slot_after_merging_list_adjacent_blocks() {
+        // Removing 1 and 3 creates two single-slot blocks that happen to be
+        // adjacent in the free list (each other's only neighbor); removing 2
+        // then merges them into a single 1..=3 block. All three freed slots
+        // must still be reachable afterwards.
+        let mut x = HopSlotMapVec::new();
+        let keys: Vec<_> = (0..5).map(|i| x.insert(i)).collect();
+        x.remove(keys[1]);
+        x.remove(keys[3]);
+        x.remove(keys[2]);
+
+        let reused: Vec<_> = (0..3).map(|i| x.insert(100 + i)).collect();
+        for k in &reused {
+            assert!(x.contains(*k));
+        }
+        assert_eq!(x.len(), 5);
+    }
+}
@@ -0,0 +1,82 @@
+//! Type-safe keys: distinct, zero-overhead key types per map.
+//!
+//! Every `SlotMapVec<T>` used to hand out the same `SlotMapIndex`, so a key
+//! from one map would compile fine (and silently resolve, wrongly or not at
+//! all) when passed to a completely different map. The `Key` trait abstracts
+//! over the raw `{slot, version}` pair, and [`new_key_type!`] generates
+//! distinct wrapper types around it so the type system rejects mixing keys
+//! across maps. `SlotMapVec<T, K>` is parameterized over `K`, with
+//! [`DefaultKey`] preserving the original, single-key-type behavior.
+
+use crate::SlotMapIndex;
+
+/// A key type that can be minted and read back by a `SlotMapVec`.
+///
+/// Implementors must be `Copy + Eq + Hash` and carry no more information
+/// than a slot index and a version, so they stay zero-overhead compared to
+/// [`SlotMapIndex`]. Don't implement this by hand; use [`new_key_type!`].
+pub trait Key: Copy + Eq + std::hash::Hash {
+    #[doc(hidden)]
+    fn from_raw(slot: u32, version: u32) -> Self;
+    #[doc(hidden)]
+    fn slot(&self) -> u32;
+    #[doc(hidden)]
+    fn version(&self) -> u32;
+}
+
+/// The key type used by `SlotMapVec<T>` when no other key type is specified,
+/// matching the original, pre-`Key` behavior.
+pub type DefaultKey = SlotMapIndex;
+
+impl Key for SlotMapIndex {
+    fn from_raw(slot: u32, version: u32) -> Self {
+        SlotMapIndex { slot, version }
+    }
+    fn slot(&self) -> u32 {
+        self.slot
+    }
+    fn version(&self) -> u32 {
+        self.version
+    }
+}
+
+/// Generate one or more zero-overhead wrapper key types around
+/// [`SlotMapIndex`], each implementing [`Key`].
+///
+/// # Examples
+///
+/// ```
+/// # use slotmapvec::*;
+/// new_key_type! {
+///     pub struct NodeKey;
+///     pub struct EdgeKey;
+/// }
+///
+/// let mut nodes: SlotMapVec<&str, NodeKey> = SlotMapVec::with_key();
+/// let n = nodes.insert("a");
+/// assert_eq!(nodes.get(n), Some(&"a"));
+/// ```
+#[macro_export]
+macro_rules! new_key_type {
+    () => {};
+
+    ($(#[$attr:meta])* $vis:vis struct $name:ident; $($rest:tt)*) => {
+        $(#[$attr])*
+        #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+        $vis struct $name($crate::SlotMapIndex);
+
+        impl $crate::Key for $name {
+            fn from_raw(slot: u32, version: u32) -> Self {
+                $name(<$crate::SlotMapIndex as $crate::Key>::from_raw(slot, version))
+            }
+            fn slot(&self) -> u32 {
+                $crate::Key::slot(&self.0)
+            }
+            fn version(&self) -> u32 {
+                $crate::Key::version(&self.0)
+            }
+        }
+
+        $crate::new_key_type!{ $($rest)* }
+    };
+}
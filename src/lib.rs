@@ -9,6 +9,11 @@
 //! the version. Deleting and inserting more times than the maximum
 //! value of `u32` will cause overflow and index conflict bugs.
 //!
+//! `SlotMapVec<T>` hands out `SlotMapIndex` keys by default. If you keep
+//! several maps around and want the type system to reject mixing up their
+//! keys, parameterize the map with a key type generated by
+//! [`new_key_type!`] instead; see [`Key`].
+//!
 //! # Examples
 //!
 //! ```
@@ -30,7 +35,24 @@
 
 use std::mem;
 use std::ops;
+use std::fmt;
 use std::iter::IntoIterator;
+use std::marker::PhantomData;
+
+mod hop;
+pub use hop::{HopSlotMapVec, SlotMapIndex as HopSlotMapIndex};
+
+mod dense;
+pub use dense::{DenseSlotMapVec, SlotMapIndex as DenseSlotMapIndex};
+
+mod secondary;
+pub use secondary::SecondaryMapVec;
+
+mod key;
+pub use key::{DefaultKey, Key};
+
+#[cfg(feature = "serde")]
+mod serde_impl;
 
 
 /// Slot map: array storage with persistent indices
@@ -39,7 +61,7 @@ use std::iter::IntoIterator;
 ///
 /// [module documentation]: index.html
 #[derive(Clone,Debug)]
-pub struct SlotMapVec<T> {
+pub struct SlotMapVec<T, K: Key = DefaultKey> {
     // Backing storage
     entries: Vec<Entry<T>>,
 
@@ -50,53 +72,159 @@ pub struct SlotMapVec<T> {
     // Number of elements stored in the map.
     // Number of free slots can be calculated by taking entries.len() - len.
     len: usize,
+
+    _key: PhantomData<K>,
 }
 
 /// An index into a `SlotMapVec`.
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+///
+/// This is the [`DefaultKey`] used when a `SlotMapVec` isn't parameterized
+/// with a type generated by [`new_key_type!`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct SlotMapIndex {
-    slot: u32,
-    version: u32,
+    pub(crate) slot: u32,
+    pub(crate) version: u32,
 }
 
-impl<T> Default for SlotMapVec<T> {
+impl<T, K: Key> Default for SlotMapVec<T, K> {
     fn default() -> Self {
-        SlotMapVec::new()
+        SlotMapVec::with_capacity(0)
     }
 }
 
-// TODO: switch to this entry type to save one word.
-//  #[derive(Clone,Debug)]
-//   pub enum Entry<T> {
-//       Free(u32, u32),
-//       Occupied(u32, T),
-//   }
+// Occupancy is encoded in the low bit of `version` (odd = occupied, even =
+// vacant) instead of a separate enum discriminant, so a slot is one word
+// (plus alignment) smaller than `{ version: u32, content: Occupation<T> }`.
+union SlotUnion<T> {
+    value: mem::ManuallyDrop<T>,
+    next_free: u32,
+}
 
-#[derive(Clone,Debug)]
 struct Entry<T> {
     version: u32,
-    content: Occupation<T>,
+    data: SlotUnion<T>,
 }
 
-#[derive(Clone,Debug)]
-enum Occupation<T> {
-    Free(usize),
-    Occupied(T),
+impl<T> Entry<T> {
+    fn is_occupied(&self) -> bool {
+        self.version % 2 == 1
+    }
+
+    fn occupied(version: u32, val: T) -> Entry<T> {
+        Entry {
+            version,
+            data: SlotUnion { value: mem::ManuallyDrop::new(val) },
+        }
+    }
+
+    fn vacant(version: u32, next_free: usize) -> Entry<T> {
+        Entry {
+            version,
+            data: SlotUnion { next_free: next_free as u32 },
+        }
+    }
+
+    // Safety: caller must have checked `is_occupied()`.
+    unsafe fn value(&self) -> &T {
+        &self.data.value
+    }
+
+    // Safety: caller must have checked `is_occupied()`.
+    unsafe fn value_mut(&mut self) -> &mut T {
+        &mut self.data.value
+    }
+
+    // Safety: caller must have checked `!is_occupied()`.
+    unsafe fn next_free(&self) -> usize {
+        self.data.next_free as usize
+    }
+
+    // Replaces the entry with a vacant one, returning the previously stored
+    // value without dropping it. Panics if the entry was already vacant.
+    fn take_occupied(&mut self, version: u32, next_free: usize) -> T {
+        assert!(self.is_occupied(), "inconsistent internal state in SlotMapVec");
+        let val = unsafe { mem::ManuallyDrop::take(&mut self.data.value) };
+        // Overwrite the fields directly instead of `*self = Entry::vacant(..)`:
+        // the latter would drop the old `self` first, and since `version` is
+        // still odd at that point, `Entry::drop` would see it as occupied and
+        // drop the value we just moved out of it a second time.
+        self.version = version;
+        self.data = SlotUnion { next_free: next_free as u32 };
+        val
+    }
+}
+
+impl<T> Drop for Entry<T> {
+    fn drop(&mut self) {
+        if self.is_occupied() {
+            unsafe { mem::ManuallyDrop::drop(&mut self.data.value) }
+        }
+    }
+}
+
+impl<T: Clone> Clone for Entry<T> {
+    fn clone(&self) -> Self {
+        if self.is_occupied() {
+            Entry::occupied(self.version, unsafe { self.value() }.clone())
+        } else {
+            Entry::vacant(self.version, unsafe { self.next_free() })
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Entry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut s = f.debug_struct("Entry");
+        s.field("version", &self.version);
+        if self.is_occupied() {
+            s.field("value", unsafe { self.value() });
+        } else {
+            s.field("next_free", &unsafe { self.next_free() });
+        }
+        s.finish()
+    }
 }
 
 /// An iterator over the values stored in a `SlotMapVec`.
-pub struct Iter<'a, T: 'a> {
+pub struct Iter<'a, T: 'a, K: Key = DefaultKey> {
     entries: std::slice::Iter<'a, Entry<T>>,
     curr: usize,
+    _key: PhantomData<K>,
 }
 
 /// A mutable iterator over the values stored in a `SlotMapVec`.
-pub struct IterMut<'a, T: 'a> {
+pub struct IterMut<'a, T: 'a, K: Key = DefaultKey> {
     entries: std::slice::IterMut<'a, Entry<T>>,
     curr: usize,
+    _key: PhantomData<K>,
+}
+
+/// An iterator that drains all values out of a `SlotMapVec`, returned by
+/// [`SlotMapVec::drain`].
+pub struct Drain<T, K: Key = DefaultKey> {
+    entries: std::iter::Enumerate<std::vec::IntoIter<Entry<T>>>,
+    _key: PhantomData<K>,
 }
 
-impl<T> SlotMapVec<T> {
+impl<T, K: Key> Iterator for Drain<T, K> {
+    type Item = (K, T);
+
+    fn next(&mut self) -> Option<(K, T)> {
+        for (slot, mut entry) in &mut self.entries {
+            if entry.is_occupied() {
+                let key = K::from_raw(slot as u32, entry.version);
+                let val = unsafe { mem::ManuallyDrop::take(&mut entry.data.value) };
+                // The value has been moved out; clear the occupied bit so
+                // `entry`'s `Drop` impl doesn't try to drop it again.
+                entry.version &= !1;
+                return Some((key, val));
+            }
+        }
+        None
+    }
+}
+
+impl<T> SlotMapVec<T, DefaultKey> {
     /// Construct a new, empty `SlotMapVec`.
     ///
     /// The function does not allocate.
@@ -107,37 +235,36 @@ impl<T> SlotMapVec<T> {
     /// # use slotmapvec::*;
     /// let slotmap :SlotMapVec<i32> = SlotMapVec::new();
     /// ```
-    pub fn new() -> SlotMapVec<T> {
+    pub fn new() -> SlotMapVec<T, DefaultKey> {
         SlotMapVec::with_capacity(0)
     }
+}
 
+impl<T, K: Key> SlotMapVec<T, K> {
     /// Construct a new `SlotMapVec` with the specified capacity.
-    pub fn with_capacity(capacity: usize) -> SlotMapVec<T> {
+    pub fn with_capacity(capacity: usize) -> SlotMapVec<T, K> {
         SlotMapVec {
             entries: Vec::with_capacity(capacity),
             len: 0,
             next_free: 0,
+            _key: PhantomData,
         }
     }
 
+    /// Construct a new, empty `SlotMapVec` with an explicit key type.
+    ///
+    /// Equivalent to [`SlotMapVec::new`], but useful when the key type
+    /// can't be inferred from the call site, e.g. with a key type generated
+    /// by [`new_key_type!`].
+    pub fn with_key() -> SlotMapVec<T, K> {
+        SlotMapVec::with_capacity(0)
+    }
+
     /// Returns the number of values the map can store without reallocating.
     pub fn capacity(&self) -> usize {
         self.entries.capacity()
     }
 
-    // pub fn reserve(&mut self, additional: usize) {
-    //     if self.capacity() - self.len + self.free_list.len() >= additional {
-    //         return;
-    //     }
-    //     let need = self.len() + additional;
-    //     self.entries.reserve(need);
-    // }
-    // pub fn clear(&mut self) {
-    //    self.entries.clear();
-    //    self.len = 0;
-    //    self.
-    //
-
     /// Returns the number of stored values.
     pub fn len(&self) -> usize {
         self.len
@@ -167,10 +294,11 @@ impl<T> SlotMapVec<T> {
     ///   i += 1;
     /// }
     /// ```
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<'_, T, K> {
         Iter {
             entries: self.entries.iter(),
             curr: 0,
+            _key: PhantomData,
         }
     }
 
@@ -194,10 +322,11 @@ impl<T> SlotMapVec<T> {
     ///   i += 2;
     /// }
     /// ```
-    pub fn iter_mut(&mut self) -> IterMut<T> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, K> {
         IterMut {
             entries: self.entries.iter_mut(),
             curr: 0,
+            _key: PhantomData,
         }
     }
 
@@ -205,14 +334,10 @@ impl<T> SlotMapVec<T> {
     ///
     /// If the given key is not associated with a values, then `None` is
     /// returned.
-    pub fn get(&self, key: SlotMapIndex) -> Option<&T> {
-        match self.entries.get(key.slot as usize) {
-            Some(&Entry { ref version, content: Occupation::Occupied(ref obj) }) => {
-                if *version == key.version {
-                    Some(obj)
-                } else {
-                    None
-                }
+    pub fn get(&self, key: K) -> Option<&T> {
+        match self.entries.get(key.slot() as usize) {
+            Some(entry) if entry.is_occupied() && entry.version == key.version() => {
+                Some(unsafe { entry.value() })
             }
             _ => None,
         }
@@ -222,14 +347,10 @@ impl<T> SlotMapVec<T> {
     ///
     /// If the given key is not associated with a values, then `None` is
     /// returned.
-    pub fn get_mut(&mut self, key: SlotMapIndex) -> Option<&mut T> {
-        match self.entries.get_mut(key.slot as usize) {
-            Some(&mut Entry { ref version, content: Occupation::Occupied(ref mut obj) }) => {
-                if *version == key.version {
-                    Some(obj)
-                } else {
-                    None
-                }
+    pub fn get_mut(&mut self, key: K) -> Option<&mut T> {
+        match self.entries.get_mut(key.slot() as usize) {
+            Some(entry) if entry.is_occupied() && entry.version == key.version() => {
+                Some(unsafe { entry.value_mut() })
             }
             _ => None,
         }
@@ -250,38 +371,21 @@ impl<T> SlotMapVec<T> {
     /// let key = map.insert("hello");
     /// assert_eq!(map[key], "hello");
     /// ```
-    pub fn insert(&mut self, val: T) -> SlotMapIndex {
+    pub fn insert(&mut self, val: T) -> K {
         if self.next_free == self.entries.len() {
             let slot = self.next_free;
-            self.entries.push(Entry {
-                version: 0,
-                content: Occupation::Occupied(val),
-            });
+            // First use of this slot: start at version 1 (odd == occupied).
+            self.entries.push(Entry::occupied(1, val));
             self.next_free += 1;
             self.len += 1;
-            SlotMapIndex {
-                slot: slot as u32,
-                version: 0,
-            }
+            K::from_raw(slot as u32, 1)
         } else {
             let slot = self.next_free;
-            let version = self.entries[slot].version + 1;
-            let prev = mem::replace(&mut self.entries[slot],
-                                    Entry {
-                                        version: version,
-                                        content: Occupation::Occupied(val),
-                                    });
-            match prev {
-                Entry { content: Occupation::Free(next), .. } => {
-                    self.next_free = next;
-                }
-                _ => panic!("inconsistent internal state in SlotMapVec"),
-            }
+            let version = self.entries[slot].version | 1;
+            self.next_free = unsafe { self.entries[slot].next_free() };
+            self.entries[slot] = Entry::occupied(version, val);
             self.len += 1;
-            SlotMapIndex {
-                slot: slot as u32,
-                version: version,
-            }
+            K::from_raw(slot as u32, version)
         }
     }
 
@@ -289,101 +393,163 @@ impl<T> SlotMapVec<T> {
     ///
     /// The key is never reused in this map, except if the underlying
     /// storage type overflows.
-    pub fn remove(&mut self, key: SlotMapIndex) -> Option<T> {
-        match self.entries.get_mut(key.slot as usize) {
-            Some(entry) => {
-                if entry.version != key.version {
-                    None
-                } else if let Occupation::Free(_) = entry.content {
-                    None
-                } else {
-                    let prev = mem::replace(&mut entry.content, Occupation::Free(self.next_free));
-                    self.next_free = key.slot as usize;
-                    self.len -= 1;
-                    match prev {
-                        Occupation::Occupied(o) => Some(o),
-                        _ => unreachable!(),
-                    }
-                }
+    pub fn remove(&mut self, key: K) -> Option<T> {
+        match self.entries.get_mut(key.slot() as usize) {
+            Some(entry) if entry.is_occupied() && entry.version == key.version() => {
+                let val = entry.take_occupied(key.version() + 1, self.next_free);
+                self.next_free = key.slot() as usize;
+                self.len -= 1;
+                Some(val)
             }
             _ => None,
         }
     }
 
     /// Return `true` if a value is associated with the given key.
-    pub fn contains(&self, key: SlotMapIndex) -> bool {
-        match self.entries.get(key.slot as usize) {
-            Some(&Entry { ref version, content: Occupation::Occupied(_) }) => {
-                *version == key.version
-            }
+    pub fn contains(&self, key: K) -> bool {
+        match self.entries.get(key.slot() as usize) {
+            Some(entry) => entry.is_occupied() && entry.version == key.version(),
             _ => false,
         }
     }
-}
 
-impl<T> ops::Index<SlotMapIndex> for SlotMapVec<T> {
-    type Output = T;
-    fn index(&self, key: SlotMapIndex) -> &T {
-        match self.entries[key.slot as usize] {
-            Entry { ref version, content: Occupation::Occupied(ref obj) } => {
-                if *version != key.version {
-                    panic!("invalid key")
-                } else {
-                    obj
-                }
+    /// Returns mutable references to the values associated with up to `N`
+    /// distinct keys at once.
+    ///
+    /// Returns `None` if any key is invalid, or if two keys refer to the
+    /// same slot. This makes it possible to mutate several elements of the
+    /// map at the same time, which the borrow checker otherwise forbids
+    /// through repeated calls to [`get_mut`](Self::get_mut).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slotmapvec::*;
+    /// let mut map = SlotMapVec::new();
+    /// let a = map.insert(1);
+    /// let b = map.insert(2);
+    ///
+    /// if let Some([a, b]) = map.get_disjoint_mut([a, b]) {
+    ///     std::mem::swap(a, b);
+    /// }
+    /// assert_eq!(map[a], 2);
+    /// assert_eq!(map[b], 1);
+    /// ```
+    pub fn get_disjoint_mut<const N: usize>(&mut self, keys: [K; N]) -> Option<[&mut T; N]> {
+        for (i, key) in keys.iter().enumerate() {
+            if !self.contains(*key) {
+                return None;
+            }
+            if keys[..i].iter().any(|other| other.slot() == key.slot()) {
+                return None;
             }
-            _ => panic!("invalid key"),
         }
+
+        // Safety: every key was checked above to refer to a distinct,
+        // occupied slot, so the returned references cannot alias.
+        let base = self.entries.as_mut_ptr();
+        Some(keys.map(|key| unsafe { (&mut *base.add(key.slot() as usize)).value_mut() }))
     }
-}
 
-impl<T> ops::IndexMut<SlotMapIndex> for SlotMapVec<T> {
-    fn index_mut(&mut self, key: SlotMapIndex) -> &mut T {
-        match self.entries[key.slot as usize] {
-            Entry { ref version, content: Occupation::Occupied(ref mut obj) } => {
-                if *version != key.version {
-                    panic!("invalid key")
-                } else {
-                    obj
+    /// Retains only the elements for which `f` returns `true`, dropping the
+    /// rest and reclaiming their slots for future `insert`s.
+    pub fn retain(&mut self, mut f: impl FnMut(K, &mut T) -> bool) {
+        for slot in 0..self.entries.len() {
+            let keep = {
+                let entry = &mut self.entries[slot];
+                if !entry.is_occupied() {
+                    continue;
                 }
+                let key = K::from_raw(slot as u32, entry.version);
+                f(key, unsafe { entry.value_mut() })
+            };
+            if !keep {
+                let next_free = self.next_free;
+                let entry = &mut self.entries[slot];
+                entry.take_occupied(entry.version + 1, next_free);
+                self.next_free = slot;
+                self.len -= 1;
+            }
+        }
+    }
+
+    /// Drops all values, invalidating every outstanding key, and rebuilds a
+    /// fresh free list so the map can be reused without reallocating.
+    pub fn clear(&mut self) {
+        let n = self.entries.len();
+        for slot in 0..n {
+            let entry = &mut self.entries[slot];
+            if entry.is_occupied() {
+                let version = entry.version + 1;
+                entry.take_occupied(version, slot + 1);
+            } else {
+                *entry = Entry::vacant(entry.version, slot + 1);
             }
-            _ => panic!("invalid key"),
         }
+        self.next_free = 0;
+        self.len = 0;
+    }
+
+    /// Removes all elements, returning them as an iterator of `(key, value)`
+    /// pairs. The map is empty once the iterator is dropped (or exhausted).
+    pub fn drain(&mut self) -> Drain<T, K> {
+        self.next_free = 0;
+        self.len = 0;
+        Drain {
+            entries: mem::take(&mut self.entries).into_iter().enumerate(),
+            _key: PhantomData,
+        }
+    }
+
+    /// Reserves capacity for at least `additional` more elements, without
+    /// panicking on allocation failure.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), std::collections::TryReserveError> {
+        self.entries.try_reserve(additional)
+    }
+}
+
+impl<T, K: Key> ops::Index<K> for SlotMapVec<T, K> {
+    type Output = T;
+    fn index(&self, key: K) -> &T {
+        self.get(key).expect("invalid key")
+    }
+}
+
+impl<T, K: Key> ops::IndexMut<K> for SlotMapVec<T, K> {
+    fn index_mut(&mut self, key: K) -> &mut T {
+        self.get_mut(key).expect("invalid key")
     }
 }
 
 
-impl<'a, T> IntoIterator for &'a SlotMapVec<T> {
-    type Item = (SlotMapIndex, &'a T);
-    type IntoIter = Iter<'a, T>;
+impl<'a, T, K: Key> IntoIterator for &'a SlotMapVec<T, K> {
+    type Item = (K, &'a T);
+    type IntoIter = Iter<'a, T, K>;
 
-    fn into_iter(self) -> Iter<'a, T> {
+    fn into_iter(self) -> Iter<'a, T, K> {
         self.iter()
     }
 }
 
-impl<'a, T> IntoIterator for &'a mut SlotMapVec<T> {
-    type Item = (SlotMapIndex, &'a mut T);
-    type IntoIter = IterMut<'a, T>;
+impl<'a, T, K: Key> IntoIterator for &'a mut SlotMapVec<T, K> {
+    type Item = (K, &'a mut T);
+    type IntoIter = IterMut<'a, T, K>;
 
-    fn into_iter(self) -> IterMut<'a, T> {
+    fn into_iter(self) -> IterMut<'a, T, K> {
         self.iter_mut()
     }
 }
 
-impl<'a, T> Iterator for Iter<'a, T> {
-    type Item = (SlotMapIndex, &'a T);
+impl<'a, T, K: Key> Iterator for Iter<'a, T, K> {
+    type Item = (K, &'a T);
 
-    fn next(&mut self) -> Option<(SlotMapIndex, &'a T)> {
-        while let Some(entry) = self.entries.next() {
-            let key = SlotMapIndex {
-                slot: self.curr as u32,
-                version: entry.version,
-            };
+    fn next(&mut self) -> Option<(K, &'a T)> {
+        for entry in &mut self.entries {
+            let key = K::from_raw(self.curr as u32, entry.version);
             self.curr += 1;
 
-            if let Occupation::Occupied(ref value) = entry.content {
-                return Some((key, value));
+            if entry.is_occupied() {
+                return Some((key, unsafe { entry.value() }));
             }
         }
 
@@ -391,19 +557,16 @@ impl<'a, T> Iterator for Iter<'a, T> {
     }
 }
 
-impl<'a, T> Iterator for IterMut<'a, T> {
-    type Item = (SlotMapIndex, &'a mut T);
+impl<'a, T, K: Key> Iterator for IterMut<'a, T, K> {
+    type Item = (K, &'a mut T);
 
-    fn next(&mut self) -> Option<(SlotMapIndex, &'a mut T)> {
-        while let Some(entry) = self.entries.next() {
-            let key = SlotMapIndex {
-                slot: self.curr as u32,
-                version: entry.version,
-            };
+    fn next(&mut self) -> Option<(K, &'a mut T)> {
+        for entry in &mut self.entries {
+            let key = K::from_raw(self.curr as u32, entry.version);
             self.curr += 1;
 
-            if let Occupation::Occupied(ref mut value) = entry.content {
-                return Some((key, value));
+            if entry.is_occupied() {
+                return Some((key, unsafe { entry.value_mut() }));
             }
         }
 
@@ -473,4 +636,159 @@ mod tests {
             println!("val: {:?}", v);
         }
     }
+
+    #[test]
+    fn distinct_key_types_do_not_mix() {
+        new_key_type! {
+            struct FooKey;
+            struct BarKey;
+        }
+
+        let mut foos: SlotMapVec<&str, FooKey> = SlotMapVec::with_key();
+        let mut bars: SlotMapVec<&str, BarKey> = SlotMapVec::with_key();
+
+        let f = foos.insert("foo");
+        let b = bars.insert("bar");
+
+        assert_eq!(foos.get(f), Some(&"foo"));
+        assert_eq!(bars.get(b), Some(&"bar"));
+        // `foos.get(b)` would not compile: FooKey and BarKey are distinct types.
+    }
+
+    #[test]
+    fn get_disjoint_mut_swaps_two_elements() {
+        let mut map = SlotMapVec::new();
+        let a = map.insert(1);
+        let b = map.insert(2);
+
+        let [ra, rb] = map.get_disjoint_mut([a, b]).unwrap();
+        mem::swap(ra, rb);
+
+        assert_eq!(map[a], 2);
+        assert_eq!(map[b], 1);
+    }
+
+    #[test]
+    fn get_disjoint_mut_rejects_aliasing_and_invalid_keys() {
+        let mut map = SlotMapVec::new();
+        let a = map.insert(1);
+        let b = map.insert(2);
+        map.remove(b);
+
+        assert!(map.get_disjoint_mut([a, a]).is_none());
+        assert!(map.get_disjoint_mut([a, b]).is_none());
+    }
+
+    #[test]
+    fn retain_drops_rejected_and_reuses_slots() {
+        let mut map = SlotMapVec::new();
+        let keys: Vec<_> = (0..5).map(|i| map.insert(i)).collect();
+
+        map.retain(|_, v| *v % 2 == 0);
+        assert_eq!(map.len(), 3);
+        for (i, k) in keys.iter().enumerate() {
+            assert_eq!(map.get(*k), if i % 2 == 0 { Some(&i) } else { None });
+        }
+
+        let reused = map.insert(99);
+        assert_eq!(map.get(reused), Some(&99));
+    }
+
+    #[test]
+    fn clear_invalidates_all_keys_and_allows_reuse() {
+        let mut map = SlotMapVec::new();
+        let a = map.insert(1);
+        let b = map.insert(2);
+
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.get(a), None);
+        assert_eq!(map.get(b), None);
+
+        let c = map.insert(3);
+        assert_eq!(map.get(c), Some(&3));
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn drain_yields_all_pairs_and_empties_the_map() {
+        let mut map = SlotMapVec::new();
+        let a = map.insert(1);
+        let b = map.insert(2);
+        let c = map.insert(3);
+        map.remove(b);
+
+        let mut drained: Vec<_> = map.drain().collect();
+        drained.sort_by_key(|(_, v)| *v);
+        assert_eq!(drained, vec![(a, 1), (c, 3)]);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn try_reserve_forwards_to_vec() {
+        let mut map: SlotMapVec<u32> = SlotMapVec::new();
+        assert!(map.try_reserve(16).is_ok());
+        assert!(map.capacity() >= 16);
+    }
+
+    // A fixture that records each drop in a shared counter, so tests can
+    // assert that removing an element drops it exactly once, not zero or
+    // two times (the latter is UB for non-`Copy` `T`).
+    struct DropCounter(std::rc::Rc<std::cell::Cell<usize>>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn remove_drops_the_value_exactly_once() {
+        let count = std::rc::Rc::new(std::cell::Cell::new(0));
+        let mut map = SlotMapVec::new();
+        let a = map.insert(DropCounter(count.clone()));
+
+        let val = map.remove(a).unwrap();
+        assert_eq!(count.get(), 0);
+        drop(val);
+        assert_eq!(count.get(), 1);
+    }
+
+    #[test]
+    fn retain_drops_each_rejected_value_exactly_once() {
+        let count = std::rc::Rc::new(std::cell::Cell::new(0));
+        let mut map = SlotMapVec::new();
+        for _ in 0..4 {
+            map.insert(DropCounter(count.clone()));
+        }
+
+        map.retain(|_, _| false);
+        assert_eq!(count.get(), 4);
+    }
+
+    #[test]
+    fn clear_drops_each_value_exactly_once() {
+        let count = std::rc::Rc::new(std::cell::Cell::new(0));
+        let mut map = SlotMapVec::new();
+        for _ in 0..4 {
+            map.insert(DropCounter(count.clone()));
+        }
+
+        map.clear();
+        assert_eq!(count.get(), 4);
+    }
+
+    #[test]
+    fn drain_drops_each_value_exactly_once() {
+        let count = std::rc::Rc::new(std::cell::Cell::new(0));
+        let mut map = SlotMapVec::new();
+        for _ in 0..4 {
+            map.insert(DropCounter(count.clone()));
+        }
+
+        let drained: Vec<_> = map.drain().collect();
+        assert_eq!(count.get(), 0);
+        drop(drained);
+        assert_eq!(count.get(), 4);
+    }
 }
@@ -0,0 +1,202 @@
+//! Secondary maps: attach out-of-band data to keys minted by a primary map.
+//!
+//! `SecondaryMapVec<T>` associates additional per-element data with
+//! `SlotMapIndex` keys handed out by a primary `SlotMapVec`, without
+//! touching the primary map itself. This is the common ECS pattern of
+//! keeping a small hot component array separate from the main storage: a
+//! primary map owns the canonical set of keys, and any number of secondary
+//! maps can attach extra columns of data keyed off those same indices.
+
+use std::iter::FromIterator;
+
+use crate::SlotMapIndex;
+
+/// A map from `SlotMapIndex` keys (minted elsewhere) to values of type `T`.
+#[derive(Clone, Debug)]
+pub struct SecondaryMapVec<T> {
+    entries: Vec<Option<(u32, T)>>,
+    len: usize,
+}
+
+impl<T> Default for SecondaryMapVec<T> {
+    fn default() -> Self {
+        SecondaryMapVec::new()
+    }
+}
+
+impl<T> SecondaryMapVec<T> {
+    /// Construct a new, empty `SecondaryMapVec`.
+    pub fn new() -> SecondaryMapVec<T> {
+        SecondaryMapVec {
+            entries: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of stored values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no values are stored in the map.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Associate `val` with `key`, returning the value previously associated
+    /// with it, if any. A stale value left by a since-reused slot is silently
+    /// replaced and dropped; `None` is returned in that case.
+    pub fn insert(&mut self, key: SlotMapIndex, val: T) -> Option<T> {
+        let slot = key.slot as usize;
+        if slot >= self.entries.len() {
+            self.entries.resize_with(slot + 1, || None);
+        }
+        let prev = self.entries[slot].take();
+        self.entries[slot] = Some((key.version, val));
+        match prev {
+            // Either a live value or a stale one left by a reused slot; either
+            // way the slot was already counted in `len`.
+            Some((version, val)) => {
+                if version == key.version {
+                    Some(val)
+                } else {
+                    None
+                }
+            }
+            None => {
+                self.len += 1;
+                None
+            }
+        }
+    }
+
+    /// Returns a reference to the value associated with the given key.
+    pub fn get(&self, key: SlotMapIndex) -> Option<&T> {
+        match self.entries.get(key.slot as usize) {
+            Some(Some((version, ref val))) if *version == key.version => Some(val),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the value associated with the given key.
+    pub fn get_mut(&mut self, key: SlotMapIndex) -> Option<&mut T> {
+        match self.entries.get_mut(key.slot as usize) {
+            Some(Some((version, ref mut val))) if *version == key.version => Some(val),
+            _ => None,
+        }
+    }
+
+    /// Return `true` if a value is associated with the given key.
+    pub fn contains(&self, key: SlotMapIndex) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Removes and returns the value associated with the given key.
+    pub fn remove(&mut self, key: SlotMapIndex) -> Option<T> {
+        let slot = self.entries.get_mut(key.slot as usize)?;
+        match slot {
+            Some((version, _)) if *version == key.version => {
+                self.len -= 1;
+                slot.take().map(|(_, val)| val)
+            }
+            _ => None,
+        }
+    }
+
+    /// Return an iterator over all elements of the map along with their key.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            entries: self.entries.iter().enumerate(),
+        }
+    }
+
+    /// Return an iterator over mutable references to all elements of the map
+    /// along with their key.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            entries: self.entries.iter_mut().enumerate(),
+        }
+    }
+}
+
+/// An iterator over the values stored in a `SecondaryMapVec`.
+pub struct Iter<'a, T: 'a> {
+    entries: std::iter::Enumerate<std::slice::Iter<'a, Option<(u32, T)>>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (SlotMapIndex, &'a T);
+    fn next(&mut self) -> Option<(SlotMapIndex, &'a T)> {
+        for (slot, entry) in &mut self.entries {
+            if let Some((version, ref val)) = *entry {
+                return Some((SlotMapIndex { slot: slot as u32, version }, val));
+            }
+        }
+        None
+    }
+}
+
+/// A mutable iterator over the values stored in a `SecondaryMapVec`.
+pub struct IterMut<'a, T: 'a> {
+    entries: std::iter::Enumerate<std::slice::IterMut<'a, Option<(u32, T)>>>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = (SlotMapIndex, &'a mut T);
+    fn next(&mut self) -> Option<(SlotMapIndex, &'a mut T)> {
+        for (slot, entry) in &mut self.entries {
+            if let Some((version, ref mut val)) = *entry {
+                return Some((SlotMapIndex { slot: slot as u32, version }, val));
+            }
+        }
+        None
+    }
+}
+
+impl<T> FromIterator<(SlotMapIndex, T)> for SecondaryMapVec<T> {
+    fn from_iter<I: IntoIterator<Item = (SlotMapIndex, T)>>(iter: I) -> Self {
+        let mut map = SecondaryMapVec::new();
+        for (key, val) in iter {
+            map.insert(key, val);
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SlotMapVec;
+
+    #[test]
+    fn attaches_data_to_primary_keys() {
+        let mut primary = SlotMapVec::new();
+        let a = primary.insert("a");
+        let b = primary.insert("b");
+
+        let mut secondary = SecondaryMapVec::new();
+        secondary.insert(a, 1);
+        secondary.insert(b, 2);
+
+        assert_eq!(secondary.get(a), Some(&1));
+        assert_eq!(secondary.get(b), Some(&2));
+        assert_eq!(secondary.len(), 2);
+    }
+
+    #[test]
+    fn stale_entries_are_replaced() {
+        let mut primary = SlotMapVec::new();
+        let a = primary.insert("a");
+
+        let mut secondary = SecondaryMapVec::new();
+        secondary.insert(a, 1);
+
+        primary.remove(a);
+        let a2 = primary.insert("a2");
+
+        assert_eq!(secondary.get(a2), None);
+        secondary.insert(a2, 2);
+        assert_eq!(secondary.get(a2), Some(&2));
+        assert_eq!(secondary.len(), 1);
+    }
+}
@@ -0,0 +1,224 @@
+//! Serde support for `SlotMapVec`, preserving slots, versions, and the free
+//! list across a round trip.
+//!
+//! Plain compaction would break previously-handed-out keys, so this
+//! serializes every slot's version and occupancy, not just the live values.
+//! Deserialization keeps the serialized `next_free`, `len` and free-list
+//! links, after checking `len` against the occupied slots and walking the
+//! free list from `next_free` to make sure it visits every vacant slot once.
+//! A malformed payload (an out-of-range or cyclic free list, or a length
+//! mismatch) is rejected instead of producing a map that would later panic
+//! in `insert`.
+
+use std::marker::PhantomData;
+
+use serde::ser::SerializeStruct;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Entry, Key, SlotMapIndex, SlotMapVec};
+
+#[derive(Serialize, Deserialize)]
+enum SerEntry<V> {
+    Occupied { version: u32, value: V },
+    Vacant { version: u32, next_free: u32 },
+}
+
+impl<T: Serialize, K: Key> Serialize for SlotMapVec<T, K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entries: Vec<SerEntry<&T>> = self
+            .entries
+            .iter()
+            .map(|e| {
+                if e.is_occupied() {
+                    SerEntry::Occupied {
+                        version: e.version,
+                        value: unsafe { e.value() },
+                    }
+                } else {
+                    SerEntry::Vacant {
+                        version: e.version,
+                        next_free: unsafe { e.next_free() as u32 },
+                    }
+                }
+            })
+            .collect();
+
+        let mut state = serializer.serialize_struct("SlotMapVec", 3)?;
+        state.serialize_field("entries", &entries)?;
+        state.serialize_field("next_free", &self.next_free)?;
+        state.serialize_field("len", &self.len)?;
+        state.end()
+    }
+}
+
+#[derive(Deserialize)]
+struct RawSlotMapVec<T> {
+    entries: Vec<SerEntry<T>>,
+    next_free: usize,
+    len: usize,
+}
+
+impl<'de, T: Deserialize<'de>, K: Key> Deserialize<'de> for SlotMapVec<T, K> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = RawSlotMapVec::<T>::deserialize(deserializer)?;
+        let total = raw.entries.len();
+
+        let entries: Vec<Entry<T>> = raw
+            .entries
+            .into_iter()
+            .map(|e| match e {
+                SerEntry::Occupied { version, value } => {
+                    if version % 2 == 0 {
+                        return Err(de::Error::custom(format!(
+                            "SlotMapVec: occupied entry has an even version ({})",
+                            version,
+                        )));
+                    }
+                    Ok(Entry::occupied(version, value))
+                }
+                SerEntry::Vacant { version, next_free } => {
+                    if version % 2 == 1 {
+                        return Err(de::Error::custom(format!(
+                            "SlotMapVec: vacant entry has an odd version ({})",
+                            version,
+                        )));
+                    }
+                    Ok(Entry::vacant(version, next_free as usize))
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        let occupied = entries.iter().filter(|e| e.is_occupied()).count();
+        if occupied != raw.len {
+            return Err(de::Error::custom(format!(
+                "SlotMapVec: `len` ({}) does not match the number of occupied entries ({})",
+                raw.len, occupied,
+            )));
+        }
+
+        let expected_free = total - raw.len;
+        let mut visited = vec![false; total];
+        let mut cur = raw.next_free;
+        let mut free_count = 0;
+        while cur != total {
+            if cur > total {
+                return Err(de::Error::custom("SlotMapVec: free list index out of range"));
+            }
+            if visited[cur] {
+                return Err(de::Error::custom("SlotMapVec: cycle in free list"));
+            }
+            if entries[cur].is_occupied() {
+                return Err(de::Error::custom("SlotMapVec: free list points at an occupied slot"));
+            }
+            visited[cur] = true;
+            free_count += 1;
+            cur = unsafe { entries[cur].next_free() };
+        }
+        if free_count != expected_free || visited.iter().zip(&entries).any(|(seen, e)| !seen && !e.is_occupied()) {
+            return Err(de::Error::custom(
+                "SlotMapVec: free list does not cover every vacant slot",
+            ));
+        }
+
+        Ok(SlotMapVec {
+            entries,
+            next_free: raw.next_free,
+            len: raw.len,
+            _key: PhantomData,
+        })
+    }
+}
+
+impl Serialize for SlotMapIndex {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("SlotMapIndex", 2)?;
+        state.serialize_field("slot", &self.slot)?;
+        state.serialize_field("version", &self.version)?;
+        state.end()
+    }
+}
+
+#[derive(Deserialize)]
+struct RawSlotMapIndex {
+    slot: u32,
+    version: u32,
+}
+
+impl<'de> Deserialize<'de> for SlotMapIndex {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = RawSlotMapIndex::deserialize(deserializer)?;
+        Ok(SlotMapIndex {
+            slot: raw.slot,
+            version: raw.version,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SlotMapVec;
+
+    #[test]
+    fn round_trip_preserves_keys() {
+        let mut map = SlotMapVec::new();
+        let a = map.insert("a");
+        let b = map.insert("b");
+        map.remove(a);
+        let c = map.insert("c");
+
+        let json = serde_json::to_string(&map).unwrap();
+        let restored: SlotMapVec<&str> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.get(a), None);
+        assert_eq!(restored.get(b), Some(&"b"));
+        assert_eq!(restored.get(c), Some(&"c"));
+
+        // The restored map's free list must still support further inserts.
+        let mut restored = restored;
+        let d = restored.insert("d");
+        assert_eq!(restored.get(d), Some(&"d"));
+    }
+
+    #[test]
+    fn rejects_cyclic_free_list() {
+        let json = r#"{
+            "entries": [
+                {"Vacant": {"version": 0, "next_free": 1}},
+                {"Vacant": {"version": 0, "next_free": 0}}
+            ],
+            "next_free": 0,
+            "len": 0
+        }"#;
+        let result: Result<SlotMapVec<i32>, _> = serde_json::from_str(json);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn rejects_vacant_entry_with_odd_version() {
+        // `is_occupied()` is defined purely in terms of version parity, so a
+        // `Vacant` tag with an odd version would deserialize into a slot that
+        // reads as occupied while its union still holds free-list data.
+        let json = r#"{
+            "entries": [
+                {"Vacant": {"version": 1, "next_free": 1}}
+            ],
+            "next_free": 0,
+            "len": 0
+        }"#;
+        let result: Result<SlotMapVec<i32>, _> = serde_json::from_str(json);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn rejects_occupied_entry_with_even_version() {
+        let json = r#"{
+            "entries": [
+                {"Occupied": {"version": 0, "value": 42}}
+            ],
+            "next_free": 1,
+            "len": 1
+        }"#;
+        let result: Result<SlotMapVec<i32>, _> = serde_json::from_str(json);
+        assert!(result.is_err());
+    }
+}